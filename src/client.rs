@@ -1,28 +1,236 @@
 use crate::error::MaasError;
+use crate::models::{Machine, PowerState, Subnet, Tag};
 
-use anyhow::{Context};
-use oauth1_request::{authorize, signature_method::HmacSha1, Credentials, Token};
+use oauth1_request::{authorize, signature_method::HmacSha1, Credentials, ParameterList, Token};
+use rand::Rng;
+use secrecy::{ExposeSecret, SecretString};
 use serde_json::Value;
+use std::time::Duration;
+use url::Url;
 
+/// Number of retries `MaasClient` performs by default before giving up.
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Starting point for the exponential backoff, before jitter is applied.
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Upper bound on any single backoff sleep, regardless of attempt count or `Retry-After`.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// HTTP statuses MAAS can return that are worth retrying.
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    matches!(
+        status.as_u16(),
+        408 | 429 | 500 | 502 | 503 | 504
+    )
+}
+
+/// Computes the delay before the next retry attempt.
+///
+/// Prefers a server-provided `Retry-After` value; otherwise backs off
+/// exponentially from `BASE_RETRY_DELAY`, applying full jitter in `[0.5, 1.0]`
+/// and capping at `MAX_RETRY_DELAY`.
+fn backoff_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    if let Some(delay) = retry_after {
+        return delay.min(MAX_RETRY_DELAY);
+    }
+
+    let exp_ms = BASE_RETRY_DELAY.as_millis().saturating_mul(1u128 << attempt.min(20));
+    let capped_ms = exp_ms.min(MAX_RETRY_DELAY.as_millis()) as u64;
+    let jitter = rand::thread_rng().gen_range(0.5..=1.0);
+    Duration::from_millis((capped_ms as f64 * jitter) as u64)
+}
+
+/// Parses a `Retry-After` header value, which is either a number of seconds
+/// or an HTTP-date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let target = httpdate::parse_http_date(value).ok()?;
+    target.duration_since(std::time::SystemTime::now()).ok()
+}
+
+/// Builds the final `?op=...` URL for a MAAS operation call, appending `op`
+/// with `&` instead of `?` if `endpoint` already carries a query string.
+fn build_op_url(base_url: &str, api_version: &str, endpoint: &str, op: &str) -> String {
+    let base = format!("{}/api/{}/{}", base_url, api_version, endpoint.trim_start_matches('/'));
+    let separator = if base.contains('?') { '&' } else { '?' };
+    format!("{}{}op={}", base, separator, op)
+}
+
+/// Splits a URL into a query-less base URI and its query parameters.
+///
+/// `oauth1_request::authorize` asserts its `uri` argument carries no query
+/// part, so any query string (`?op=...`, `?page=...`) has to be pulled out
+/// and signed separately, as OAuth1 `Request` data, rather than concatenated
+/// onto the URI before signing. Falls back to treating the whole string as
+/// the base with no query params if it doesn't parse as a URL.
+fn split_query(url: &str) -> (String, Vec<(String, String)>) {
+    match Url::parse(url) {
+        Ok(mut parsed) => {
+            let params = parsed.query_pairs().into_owned().collect();
+            parsed.set_query(None);
+            (parsed.into(), params)
+        }
+        Err(_) => (url.to_string(), Vec::new()),
+    }
+}
+
+/// Extracts the `rel="next"` URL from a `Link` header value, RFC 5988 style:
+/// `<https://maas/.../?page=2>; rel="next", <...>; rel="prev"`.
+#[cfg(feature = "async")]
+fn next_page_link(link_header: &str) -> Option<String> {
+    link_header.split(',').find_map(|part| {
+        let mut segments = part.split(';');
+        let url = segments.next()?.trim().trim_start_matches('<').trim_end_matches('>');
+        segments
+            .any(|seg| seg.trim() == "rel=\"next\"")
+            .then(|| url.to_string())
+    })
+}
+
+/// The body of an outgoing request, and how it should be encoded.
+enum RequestBody {
+    /// A JSON body, used by the plain REST `get`/`post`/`put`/`delete` methods.
+    Json(Value),
+    /// A form-urlencoded body, used by MAAS "operation" calls (`?op=...`).
+    Form(Vec<(String, String)>),
+}
+
+/// Builds a `MaasClient` with custom TLS settings, for MAAS servers fronted by a
+/// self-signed certificate or an internal CA.
+pub struct MaasClientBuilder {
+    base_url: String,
+    api_key: String,
+    api_version: String,
+    root_cert_pem: Option<Vec<u8>>,
+    danger_accept_invalid_certs: bool,
+}
+
+impl MaasClientBuilder {
+    pub fn new(base_url: &str, api_key: &str, api_version: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            api_key: api_key.to_string(),
+            api_version: api_version.to_string(),
+            root_cert_pem: None,
+            danger_accept_invalid_certs: false,
+        }
+    }
+
+    /// Trusts an additional root certificate (PEM-encoded bytes) for this MAAS server,
+    /// on top of the system's native roots.
+    pub fn with_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_cert_pem = Some(pem.into());
+        self
+    }
+
+    /// Reads a PEM root certificate from disk; see `with_root_certificate`.
+    pub fn with_root_certificate_file(self, path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let pem = std::fs::read(path)?;
+        Ok(self.with_root_certificate(pem))
+    }
+
+    /// Disables TLS certificate verification entirely.
+    ///
+    /// Dangerous: only use this against a MAAS instance you control, e.g. in local
+    /// development, never in production.
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.danger_accept_invalid_certs = accept;
+        self
+    }
+
+    fn root_certificate(&self) -> Result<Option<reqwest::Certificate>, MaasError> {
+        self.root_cert_pem
+            .as_deref()
+            .map(|pem| reqwest::Certificate::from_pem(pem).map_err(|e| MaasError::TlsConfig(e.to_string())))
+            .transpose()
+    }
+
+    /// Builds the async `MaasClient`, using rustls with the system's native roots
+    /// plus any certificate supplied via `with_root_certificate`.
+    #[cfg(feature = "async")]
+    pub fn build(self) -> anyhow::Result<client::MaasClient, MaasError> {
+        let mut builder = reqwest::Client::builder().use_rustls_tls();
+
+        if let Some(cert) = self.root_certificate()? {
+            builder = builder.add_root_certificate(cert);
+        }
+        if self.danger_accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        let http_client = builder.build().map_err(|e| MaasError::TlsConfig(e.to_string()))?;
+
+        client::MaasClient::with_client(&self.base_url, &self.api_key, &self.api_version, http_client)
+    }
+
+    /// Builds the blocking `MaasClient`; see `build` for the TLS behavior.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> anyhow::Result<blocking_client::MaasClient> {
+        let mut builder = reqwest::blocking::Client::builder().use_rustls_tls();
+
+        if let Some(cert) = self.root_certificate()? {
+            builder = builder.add_root_certificate(cert);
+        }
+        if self.danger_accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        let http_client = builder.build().map_err(|e| MaasError::TlsConfig(e.to_string()))?;
+
+        Ok(blocking_client::MaasClient::with_client(&self.base_url, &self.api_key, &self.api_version, http_client)?)
+    }
+}
 
 // Default async client
 #[cfg(feature = "async")]
+#[allow(clippy::module_inception)]
 pub mod client{
     use super::*;
+    use futures::stream::{self, Stream};
     use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+    use serde::de::DeserializeOwned;
 
-    #[derive(Debug)]
     pub struct MaasClient {
         pub(crate) base_url: String,
-        consumer_key: String,
-        token_key: String,
-        token_secret: String,
+        consumer_key: SecretString,
+        token_key: SecretString,
+        token_secret: SecretString,
         pub(crate) api_version: String,
+        max_retries: u32,
         client: reqwest::Client,
     }
 
+    impl std::fmt::Debug for MaasClient {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("MaasClient")
+                .field("base_url", &self.base_url)
+                .field("consumer_key", &"***")
+                .field("token_key", &"***")
+                .field("token_secret", &"***")
+                .field("api_version", &self.api_version)
+                .field("max_retries", &self.max_retries)
+                .finish()
+        }
+    }
+
     impl MaasClient {
         pub fn new(base_url: &str, api_key: &str, api_version: &str) -> anyhow::Result<Self, MaasError> {
+            Self::with_client(base_url, api_key, api_version, reqwest::Client::new())
+        }
+
+        /// Builds a client around a caller-supplied `reqwest::Client`, e.g. one
+        /// configured with custom TLS settings by `MaasClientBuilder`.
+        pub(crate) fn with_client(
+            base_url: &str,
+            api_key: &str,
+            api_version: &str,
+            client: reqwest::Client,
+        ) -> anyhow::Result<Self, MaasError> {
             let parts: Vec<&str> = api_key.split(':').collect();
             if parts.len() != 3 {
                 return Err(MaasError::InvalidKeyFormat);
@@ -30,22 +238,35 @@ pub mod client{
 
             Ok(Self {
                 base_url: base_url.trim_end_matches('/').to_string(),
-                consumer_key: parts[0].to_string(),
-                token_key: parts[1].to_string(),
-                token_secret: parts[2].to_string(),
+                consumer_key: SecretString::new(parts[0].to_string()),
+                token_key: SecretString::new(parts[1].to_string()),
+                token_secret: SecretString::new(parts[2].to_string()),
                 api_version: api_version.to_string(),
-                client: reqwest::Client::new(),
+                max_retries: DEFAULT_MAX_RETRIES,
+                client,
             })
         }
 
+        /// Overrides the number of retries performed on transient failures (default 3).
+        pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
         /// Generates the Oauth1 header for the request
         pub(crate) fn generate_auth_header(&self, method: &str, url: &str) -> String {
             // MAAS consumer secret is empty
-            let client_creds = Credentials::new(self.consumer_key.as_str(), "");
-            let token_creds = Credentials::new(self.token_key.as_str(), self.token_secret.as_str());
+            let client_creds = Credentials::new(self.consumer_key.expose_secret().as_str(), "");
+            let token_creds = Credentials::new(self.token_key.expose_secret(), self.token_secret.expose_secret());
             let oauth_token = Token::new(client_creds, token_creds);
 
-            authorize(method, url, &(), &oauth_token, HmacSha1)
+            // `url` may carry a query string (`?op=...`, `?page=...`); `authorize`
+            // requires a query-less uri and instead wants those params signed as
+            // request data, or the signature comes out wrong (or panics in debug).
+            let (base_url, query) = split_query(url);
+            let params = ParameterList::new(query);
+
+            authorize(method, base_url, &params, &oauth_token, HmacSha1::default())
         }
 
         pub async fn get(&self, endpoint: &str) -> anyhow::Result<Value, MaasError> {
@@ -53,50 +274,222 @@ pub mod client{
         }
 
         pub async fn post(&self, endpoint: &str, body: Option<Value>) -> anyhow::Result<Value, MaasError> {
-            self.request("POST", endpoint, body).await
+            self.request("POST", endpoint, body.map(RequestBody::Json)).await
         }
 
         pub async fn put(&self, endpoint: &str, body: Option<Value>) -> anyhow::Result<Value, MaasError> {
-            self.request("PUT", endpoint, body).await
+            self.request("PUT", endpoint, body.map(RequestBody::Json)).await
         }
 
         pub async fn delete(&self, endpoint: &str) -> anyhow::Result<Value, MaasError> {
             self.request("DELETE", endpoint, None).await
         }
 
-        ///Performs HTTP requests to MAAS API
-        async fn request(&self, method: &str, endpoint: &str, body: Option<Value>) -> anyhow::Result<Value, MaasError> {
-            // authenticate and build request
+        /// Calls a MAAS "operation" endpoint, e.g. `POST /machines/{id}/?op=deploy`.
+        ///
+        /// `params` is sent as a form-urlencoded body, matching how MAAS expects
+        /// operation parameters rather than a JSON payload.
+        pub async fn operation(&self, method: &str, endpoint: &str, op: &str, params: &[(&str, &str)]) -> anyhow::Result<Value, MaasError> {
+            let form = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            self.request_with_op(method, endpoint, op, RequestBody::Form(form)).await
+        }
+
+        /// Queries the live power state of a machine.
+        pub async fn get_machine_power_state(&self, system_id: &str) -> anyhow::Result<PowerState, MaasError> {
+            let json = self.operation("GET", &format!("/machines/{}/", system_id), "query_power_state", &[]).await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Deploys a ready machine, installing the requested OS.
+        pub async fn deploy_machine(&self, system_id: &str) -> anyhow::Result<Machine, MaasError> {
+            let json = self.operation("POST", &format!("/machines/{}/", system_id), "deploy", &[]).await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Releases a deployed machine back into the ready pool.
+        pub async fn release_machine(&self, system_id: &str) -> anyhow::Result<Machine, MaasError> {
+            let json = self.operation("POST", &format!("/machines/{}/", system_id), "release", &[]).await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Commissions a machine, running MAAS's hardware discovery scripts.
+        pub async fn commission_machine(&self, system_id: &str) -> anyhow::Result<Machine, MaasError> {
+            let json = self.operation("POST", &format!("/machines/{}/", system_id), "commission", &[]).await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Lists all machines known to MAAS.
+        pub async fn list_machines(&self) -> anyhow::Result<Vec<Machine>, MaasError> {
+            let json = self.get("/machines/").await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Fetches a single machine by its system ID.
+        pub async fn get_machine(&self, system_id: &str) -> anyhow::Result<Machine, MaasError> {
+            let json = self.get(&format!("/machines/{}/", system_id)).await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Lists all tags defined in MAAS.
+        pub async fn list_tags(&self) -> anyhow::Result<Vec<Tag>, MaasError> {
+            let json = self.get("/tags/").await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Fetches a single tag by name.
+        pub async fn get_tag(&self, name: &str) -> anyhow::Result<Tag, MaasError> {
+            let json = self.get(&format!("/tags/{}/", name)).await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Lists all subnets known to MAAS.
+        pub async fn list_subnets(&self) -> anyhow::Result<Vec<Subnet>, MaasError> {
+            let json = self.get("/subnets/").await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Fetches a single subnet by ID.
+        pub async fn get_subnet(&self, id: u64) -> anyhow::Result<Subnet, MaasError> {
+            let json = self.get(&format!("/subnets/{}/", id)).await?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        ///Performs HTTP requests to MAAS API, retrying transient failures with backoff
+        async fn request(&self, method: &str, endpoint: &str, body: Option<RequestBody>) -> anyhow::Result<Value, MaasError> {
             let url = format!("{}/api/{}/{}", self.base_url, self.api_version, endpoint.trim_start_matches('/'));
-            let auth_header = self.generate_auth_header(method, &url);
-            let mut request = self.client.request(
-                method.parse().unwrap_or(reqwest::Method::GET),
-                &url,
-            );
-            request = request
-                .header(AUTHORIZATION, auth_header)
-                .header(CONTENT_TYPE, "application/json");
-
-            if let Some(body) = body {
-                request = request.json(&body);
-            }
+            self.send(method, &url, body).await
+        }
+
+        /// Builds the final `?op=...` URL for a MAAS operation call and performs it.
+        ///
+        /// The OAuth signature is computed over this final URL, `op` included, as
+        /// MAAS expects.
+        async fn request_with_op(&self, method: &str, endpoint: &str, op: &str, body: RequestBody) -> anyhow::Result<Value, MaasError> {
+            let url = build_op_url(&self.base_url, &self.api_version, endpoint, op);
+            self.send(method, &url, Some(body)).await
+        }
+
+        async fn send(&self, method: &str, url: &str, body: Option<RequestBody>) -> anyhow::Result<Value, MaasError> {
+            Ok(self.send_raw(method, url, body).await?.0)
+        }
+
+        /// Like `send`, but also returns the response headers (used by pagination
+        /// to read the `Link: rel="next"` header).
+        async fn send_raw(&self, method: &str, url: &str, body: Option<RequestBody>) -> anyhow::Result<(Value, reqwest::header::HeaderMap), MaasError> {
+            let mut attempt: u32 = 0;
+
+            loop {
+                // regenerate on every attempt: the nonce/timestamp must be fresh
+                let auth_header = self.generate_auth_header(method, url);
+                let mut request = self.client.request(
+                    method.parse().unwrap_or(reqwest::Method::GET),
+                    url,
+                );
+                request = request.header(AUTHORIZATION, auth_header);
+
+                request = match &body {
+                    Some(RequestBody::Json(value)) => request.header(CONTENT_TYPE, "application/json").json(value),
+                    Some(RequestBody::Form(pairs)) => request.form(pairs),
+                    None => request.header(CONTENT_TYPE, "application/json"),
+                };
+
+                match request.send().await {
+                    Ok(response) => {
+                        let status = response.status();
+
+                        if status.is_success() {
+                            let headers = response.headers().clone();
+                            let json: Value = response.json().await?;
+                            return Ok((json, headers));
+                        }
 
-            let response = request.send().await?;
+                        if is_retryable_status(status) {
+                            if attempt >= self.max_retries {
+                                return Err(MaasError::RetriesExhausted { attempts: attempt + 1, last_status: Some(status) });
+                            }
 
-            let status = response.status();
+                            let retry_after = response
+                                .headers()
+                                .get(reqwest::header::RETRY_AFTER)
+                                .and_then(|v| v.to_str().ok())
+                                .and_then(parse_retry_after);
+                            tokio::time::sleep(backoff_delay(attempt, retry_after)).await;
+                            attempt += 1;
+                            continue;
+                        }
 
-            if !status.is_success() {
-                let text = response.text().await.unwrap_or_default();
-                return Err(MaasError::ApiError {status, body: text});
+                        let text = response.text().await.unwrap_or_default();
+                        return Err(MaasError::ApiError {status, body: text});
+                    }
+                    Err(err) if err.is_timeout() || err.is_connect() => {
+                        if attempt >= self.max_retries {
+                            return Err(MaasError::RetriesExhausted { attempts: attempt + 1, last_status: None });
+                        }
+
+                        tokio::time::sleep(backoff_delay(attempt, None)).await;
+                        attempt += 1;
+                    }
+                    Err(err) => return Err(MaasError::Network(err)),
+                }
             }
+        }
 
-            let json: Value = response.json().await?;
+        /// Streams every item of a paginated MAAS list endpoint, fetching the next
+        /// page (following the `Link: rel="next"` header) only once the consumer
+        /// has drained the current one.
+        pub fn paginate<T>(&self, endpoint: &str) -> impl Stream<Item = anyhow::Result<T, MaasError>> + '_
+        where
+            T: DeserializeOwned,
+        {
+            let first_url = format!("{}/api/{}/{}", self.base_url, self.api_version, endpoint.trim_start_matches('/'));
+
+            stream::unfold(PageState::Fetch(Some(first_url)), move |mut state| async move {
+                loop {
+                    match state {
+                        PageState::Fetch(Some(url)) => {
+                            match self.send_raw("GET", &url, None).await {
+                                Ok((json, headers)) => {
+                                    let next = headers
+                                        .get(reqwest::header::LINK)
+                                        .and_then(|v| v.to_str().ok())
+                                        .and_then(next_page_link);
+                                    let items = json.as_array().cloned().unwrap_or_default();
+                                    state = PageState::Drain(items.into_iter(), next);
+                                }
+                                Err(err) => return Some((Err(err), PageState::Done)),
+                            }
+                        }
+                        PageState::Fetch(None) | PageState::Done => return None,
+                        PageState::Drain(mut items, next) => {
+                            if let Some(value) = items.next() {
+                                let item = serde_json::from_value(value).map_err(MaasError::from);
+                                return Some((item, PageState::Drain(items, next)));
+                            }
+                            state = PageState::Fetch(next);
+                        }
+                    }
+                }
+            })
+        }
 
-            Ok(json)
+        /// Streams every machine known to MAAS, transparently following pagination.
+        pub fn list_machines_stream(&self) -> impl Stream<Item = anyhow::Result<Machine, MaasError>> + '_ {
+            self.paginate("/machines/")
         }
     }
 }
 
+/// Pagination state driving `MaasClient::paginate`'s `futures::stream::unfold` loop.
+#[cfg(feature = "async")]
+enum PageState {
+    /// Fetch the page at this URL next; `None` means pagination is exhausted.
+    Fetch(Option<String>),
+    /// Yielding buffered items from the most recently fetched page.
+    Drain(std::vec::IntoIter<Value>, Option<String>),
+    /// A fetch failed; the stream ends after reporting the error.
+    Done,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,7 +512,7 @@ mod tests {
         // Too few parts
         let result = MaasClient::new("http://localhost:5240/MAAS", "part1:part2", "2.0");
         match result{
-            Err(MaasError::InvalidKeyFormat) => assert!(true),
+            Err(MaasError::InvalidKeyFormat) => {}
             _ => panic!("Expected InvalidKeyFormat error, instead got {:?}", result),
         }
 
@@ -170,6 +563,49 @@ mod tests {
         assert!(header.contains("oauth_signature="));
     }
 
+    #[test]
+    fn test_oauth_header_generation_signs_query_bearing_url() {
+        // `operation()` calls (deploy/release/commission/query_power_state) and
+        // paginated "next page" fetches both sign URLs with a query string
+        // attached; `authorize` panics in debug builds if that query string is
+        // passed through verbatim instead of being signed as request data.
+        let client = MaasClient::new("http://localhost", "cons:tok:sec", "2.0").unwrap();
+        let url = "http://localhost/api/2.0/machines/abc123/?op=deploy";
+
+        let header = client.generate_auth_header("POST", url);
+
+        assert!(header.contains("oauth_consumer_key=\"cons\""));
+        assert!(header.contains("oauth_signature="));
+    }
+
+    #[test]
+    fn test_oauth_header_signature_reflects_query_params() {
+        // The signature must actually depend on the query params, not just
+        // avoid panicking: signing the same path with a different `op` value
+        // should produce a different signature.
+        let client = MaasClient::new("http://localhost", "cons:tok:sec", "2.0").unwrap();
+
+        let header_deploy = client.generate_auth_header("POST", "http://localhost/api/2.0/machines/abc123/?op=deploy");
+        let header_release = client.generate_auth_header("POST", "http://localhost/api/2.0/machines/abc123/?op=release");
+
+        assert_ne!(header_deploy, header_release);
+    }
+
+    #[test]
+    fn test_split_query_separates_base_and_params() {
+        let (base, params) = split_query("http://localhost/api/2.0/machines/?filter=ready&op=deploy");
+        assert_eq!(base, "http://localhost/api/2.0/machines/");
+        assert!(params.contains(&("filter".to_string(), "ready".to_string())));
+        assert!(params.contains(&("op".to_string(), "deploy".to_string())));
+    }
+
+    #[test]
+    fn test_split_query_no_query_part() {
+        let (base, params) = split_query("http://localhost/api/2.0/machines/");
+        assert_eq!(base, "http://localhost/api/2.0/machines/");
+        assert!(params.is_empty());
+    }
+
     #[test]
     fn test_endpoint_slash_handling() {
         // When the user provides "machines/" or "/machines/", the URL shouldn't change
@@ -185,6 +621,228 @@ mod tests {
         assert_eq!(url1, "http://localhost/api/2.0/machines/");
         assert_eq!(url1, url2);
     }
+
+    // Pagination
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_next_page_link_parsing() {
+        let header = "<http://localhost/api/2.0/machines/?page=2>; rel=\"next\", <http://localhost/api/2.0/machines/?page=1>; rel=\"prev\"";
+        assert_eq!(next_page_link(header), Some("http://localhost/api/2.0/machines/?page=2".to_string()));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_next_page_link_missing() {
+        let header = "<http://localhost/api/2.0/machines/?page=1>; rel=\"prev\"";
+        assert_eq!(next_page_link(header), None);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_paginate_follows_query_bearing_next_link() {
+        // The "next" link carries a query string (?page=2); this used to
+        // panic/mis-sign via the same bug as query-bearing operation() calls.
+        use futures::StreamExt;
+        use wiremock::matchers::{method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        fn machine_json(system_id: &str) -> Value {
+            serde_json::json!({
+                "system_id": system_id,
+                "hostname": "host",
+                "power_state": "on",
+                "architecture": "amd64/generic",
+                "memory": 1024,
+                "cpu_count": 2,
+                "status_name": "Ready",
+            })
+        }
+
+        let server = MockServer::start().await;
+
+        // Scoped to the first call only: a request for page 2 must not match
+        // this mock too (it has no query constraint), or pagination loops
+        // forever re-fetching page 1's "next" link.
+        Mock::given(method("GET"))
+            .and(path("/api/2.0/machines/"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!([machine_json("a")]))
+                    .insert_header("Link", format!("<{}/api/2.0/machines/?page=2>; rel=\"next\"", server.uri()).as_str()),
+            )
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/2.0/machines/"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([machine_json("b")])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = MaasClient::new(&server.uri(), "cons:tok:sec", "2.0").unwrap();
+        let results: Vec<_> = client.list_machines_stream().collect().await;
+        let system_ids: Vec<String> = results.into_iter().map(|r| r.unwrap().system_id).collect();
+
+        assert_eq!(system_ids, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    // Retry / backoff
+
+    #[test]
+    fn test_backoff_delay_grows_exponentially() {
+        // Full jitter means each delay is only bounded, not exact, so compare
+        // the worst case (jitter = 1.0) across attempts.
+        let max_attempt_0 = BASE_RETRY_DELAY;
+        let max_attempt_1 = BASE_RETRY_DELAY * 2;
+        let max_attempt_2 = BASE_RETRY_DELAY * 4;
+
+        for _ in 0..20 {
+            assert!(backoff_delay(0, None) <= max_attempt_0);
+            assert!(backoff_delay(1, None) <= max_attempt_1);
+            assert!(backoff_delay(2, None) <= max_attempt_2);
+        }
+    }
+
+    #[test]
+    fn test_backoff_delay_respects_jitter_bounds() {
+        for attempt in 0..5 {
+            let delay = backoff_delay(attempt, None);
+            let exp_ms = BASE_RETRY_DELAY.as_millis().saturating_mul(1u128 << attempt.min(20));
+            let capped_ms = exp_ms.min(MAX_RETRY_DELAY.as_millis()) as u64;
+            let lower = Duration::from_millis((capped_ms as f64 * 0.5) as u64);
+
+            assert!(delay >= lower, "{:?} should be >= {:?} (attempt {})", delay, lower, attempt);
+            assert!(delay <= Duration::from_millis(capped_ms), "{:?} should be <= {:?} (attempt {})", delay, capped_ms, attempt);
+        }
+    }
+
+    #[test]
+    fn test_backoff_delay_caps_at_max_retry_delay() {
+        let delay = backoff_delay(30, None);
+        assert!(delay <= MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_backoff_delay_prefers_retry_after() {
+        let delay = backoff_delay(0, Some(Duration::from_secs(5)));
+        assert_eq!(delay, Duration::from_secs(5));
+
+        // Still capped, even if the server asks for something absurd
+        let delay = backoff_delay(0, Some(Duration::from_secs(3600)));
+        assert_eq!(delay, MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_parse_retry_after_seconds() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("  5  "), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_parse_retry_after_http_date() {
+        // Far enough in the future that `duration_since(now)` is always positive.
+        let parsed = parse_retry_after("Wed, 21 Oct 2099 07:28:00 GMT");
+        assert!(parsed.is_some());
+    }
+
+    #[test]
+    fn test_parse_retry_after_invalid() {
+        assert_eq!(parse_retry_after("not-a-date"), None);
+        assert_eq!(parse_retry_after(""), None);
+    }
+
+    // Operation URLs
+
+    #[test]
+    fn test_build_op_url_appends_with_question_mark() {
+        let url = build_op_url("http://localhost", "2.0", "machines/abc123/", "deploy");
+        assert_eq!(url, "http://localhost/api/2.0/machines/abc123/?op=deploy");
+    }
+
+    #[test]
+    fn test_build_op_url_appends_with_ampersand_when_query_present() {
+        let url = build_op_url("http://localhost", "2.0", "machines/?filter=ready", "deploy");
+        assert_eq!(url, "http://localhost/api/2.0/machines/?filter=ready&op=deploy");
+    }
+
+    // Retry loop (end-to-end against a mock server)
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_send_retries_transient_failure_then_succeeds() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+
+        // Scoped to the first call only, so the retry falls through to the
+        // 200 below rather than looping on 503 forever.
+        Mock::given(method("GET"))
+            .and(path("/api/2.0/machines/"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/2.0/machines/"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = MaasClient::new(&server.uri(), "cons:tok:sec", "2.0").unwrap();
+        let result = client.list_machines().await;
+
+        assert!(result.is_ok(), "expected success after one retry, got {:?}", result);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_send_gives_up_after_max_retries() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/2.0/machines/"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let client = MaasClient::new(&server.uri(), "cons:tok:sec", "2.0")
+            .unwrap()
+            .with_max_retries(1);
+        let result = client.list_machines().await;
+
+        match result {
+            Err(MaasError::RetriesExhausted { attempts, last_status }) => {
+                assert_eq!(attempts, 2);
+                assert_eq!(last_status, Some(reqwest::StatusCode::SERVICE_UNAVAILABLE));
+            }
+            other => panic!("expected RetriesExhausted, got {:?}", other),
+        }
+    }
+
+    // Debug redaction
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let client = MaasClient::new("http://localhost", "verysecretconsumer:verysecrettoken:verysecretsecret", "2.0").unwrap();
+        let debug = format!("{:?}", client);
+
+        assert!(!debug.contains("verysecretconsumer"));
+        assert!(!debug.contains("verysecrettoken"));
+        assert!(!debug.contains("verysecretsecret"));
+        assert!(debug.contains("***"));
+    }
 }
 
 
@@ -193,41 +851,80 @@ mod tests {
 #[cfg(feature="blocking")]
 pub mod blocking_client {
     use super::*;
-
+    use anyhow::{Context, Result};
+    use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 
     pub struct MaasClient {
-        base_url: String,
-        consumer_key: String,
-        token_key: String,
-        token_secret: String,
-        api_version: String,
+        pub(crate) base_url: String,
+        consumer_key: SecretString,
+        token_key: SecretString,
+        token_secret: SecretString,
+        pub(crate) api_version: String,
+        max_retries: u32,
         client: reqwest::blocking::Client,
     }
 
+    impl std::fmt::Debug for MaasClient {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("MaasClient")
+                .field("base_url", &self.base_url)
+                .field("consumer_key", &"***")
+                .field("token_key", &"***")
+                .field("token_secret", &"***")
+                .field("api_version", &self.api_version)
+                .field("max_retries", &self.max_retries)
+                .finish()
+        }
+    }
+
     impl MaasClient {
-        pub fn new(base_url: &str, api_key: &str, api_version: &str) -> anyhow::Result<Self> {
+        pub fn new(base_url: &str, api_key: &str, api_version: &str) -> anyhow::Result<Self, MaasError> {
+            Self::with_client(base_url, api_key, api_version, reqwest::blocking::Client::new())
+        }
+
+        /// Builds a client around a caller-supplied `reqwest::blocking::Client`, e.g.
+        /// one configured with custom TLS settings by `MaasClientBuilder`.
+        pub(crate) fn with_client(
+            base_url: &str,
+            api_key: &str,
+            api_version: &str,
+            client: reqwest::blocking::Client,
+        ) -> anyhow::Result<Self, MaasError> {
             let parts: Vec<&str> = api_key.split(':').collect();
             if parts.len() != 3 {
-                return Err(anyhow::anyhow!("Invalid API Key format. Expected A:B:C"));
+                return Err(MaasError::InvalidKeyFormat);
             }
 
             Ok(Self {
                 base_url: base_url.trim_end_matches('/').to_string(),
-                consumer_key: parts[0].to_string(),
-                token_key: parts[1].to_string(),
-                token_secret: parts[2].to_string(),
+                consumer_key: SecretString::new(parts[0].to_string()),
+                token_key: SecretString::new(parts[1].to_string()),
+                token_secret: SecretString::new(parts[2].to_string()),
                 api_version: api_version.to_string(),
-                client: reqwest::blocking::Client::new(),
+                max_retries: DEFAULT_MAX_RETRIES,
+                client,
             })
         }
 
-        fn generate_auth_header(&self, method: &str, url: &str) -> String {
+        /// Overrides the number of retries performed on transient failures (default 3).
+        pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+            self.max_retries = max_retries;
+            self
+        }
+
+        pub(crate) fn generate_auth_header(&self, method: &str, url: &str) -> String {
             // MAAS consumer secret is empty
-            let client_creds = Credentials::new(self.consumer_key.as_str(), "");
-            let token_creds = Credentials::new(self.token_key.as_str(), self.token_secret.as_str());
+            let client_creds = Credentials::new(self.consumer_key.expose_secret().as_str(), "");
+            let token_creds = Credentials::new(self.token_key.expose_secret(), self.token_secret.expose_secret());
             let oauth_token = Token::new(client_creds, token_creds);
 
-            authorize(method, url, &(), &oauth_token, HmacSha1)
+            // `url` may carry a query string (`?op=...`, `?page=...`); `authorize`
+            // requires a query-less uri and instead wants those params signed as
+            // request data, or the signature comes out wrong (or panics in debug).
+            let (base_url, query) = split_query(url);
+            let params = ParameterList::new(query);
+
+            authorize(method, base_url, &params, &oauth_token, HmacSha1::default())
         }
 
         pub fn get(&self, endpoint: &str) -> anyhow::Result<serde_json::Value> {
@@ -235,43 +932,156 @@ pub mod blocking_client {
         }
 
         pub fn post(&self, endpoint: &str, body: Option<Value>) -> anyhow::Result<serde_json::Value> {
-            self.request("POST", endpoint, body)
+            self.request("POST", endpoint, body.map(RequestBody::Json))
         }
 
         pub fn put(&self, endpoint: &str, body: Option<Value>) -> anyhow::Result<serde_json::Value> {
-            self.request("PUT", endpoint, body)
+            self.request("PUT", endpoint, body.map(RequestBody::Json))
         }
 
         pub fn delete(&self, endpoint: &str) -> anyhow::Result<serde_json::Value> {
             self.request("DELETE", endpoint, None)
         }
 
-        fn request(&self, method: &str, endpoint: &str, body: Option<Value>) -> Result<Value> {
+        /// Calls a MAAS "operation" endpoint, e.g. `POST /machines/{id}/?op=deploy`.
+        ///
+        /// `params` is sent as a form-urlencoded body, matching how MAAS expects
+        /// operation parameters rather than a JSON payload.
+        pub fn operation(&self, method: &str, endpoint: &str, op: &str, params: &[(&str, &str)]) -> anyhow::Result<Value> {
+            let form = params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            self.request_with_op(method, endpoint, op, RequestBody::Form(form))
+        }
+
+        /// Deploys a ready machine, installing the requested OS.
+        pub fn deploy_machine(&self, system_id: &str) -> anyhow::Result<Machine> {
+            let json = self.operation("POST", &format!("/machines/{}/", system_id), "deploy", &[])?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Releases a deployed machine back into the ready pool.
+        pub fn release_machine(&self, system_id: &str) -> anyhow::Result<Machine> {
+            let json = self.operation("POST", &format!("/machines/{}/", system_id), "release", &[])?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Commissions a machine, running MAAS's hardware discovery scripts.
+        pub fn commission_machine(&self, system_id: &str) -> anyhow::Result<Machine> {
+            let json = self.operation("POST", &format!("/machines/{}/", system_id), "commission", &[])?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Lists all machines known to MAAS.
+        pub fn list_machines(&self) -> anyhow::Result<Vec<Machine>> {
+            let json = self.get("/machines/")?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Fetches a single machine by its system ID.
+        pub fn get_machine(&self, system_id: &str) -> anyhow::Result<Machine> {
+            let json = self.get(&format!("/machines/{}/", system_id))?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Queries the live power state of a machine.
+        pub fn get_machine_power_state(&self, system_id: &str) -> anyhow::Result<PowerState> {
+            let json = self.operation("GET", &format!("/machines/{}/", system_id), "query_power_state", &[])?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Lists all tags defined in MAAS.
+        pub fn list_tags(&self) -> anyhow::Result<Vec<Tag>> {
+            let json = self.get("/tags/")?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Fetches a single tag by name.
+        pub fn get_tag(&self, name: &str) -> anyhow::Result<Tag> {
+            let json = self.get(&format!("/tags/{}/", name))?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Lists all subnets known to MAAS.
+        pub fn list_subnets(&self) -> anyhow::Result<Vec<Subnet>> {
+            let json = self.get("/subnets/")?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        /// Fetches a single subnet by ID.
+        pub fn get_subnet(&self, id: u64) -> anyhow::Result<Subnet> {
+            let json = self.get(&format!("/subnets/{}/", id))?;
+            Ok(serde_json::from_value(json)?)
+        }
+
+        fn request(&self, method: &str, endpoint: &str, body: Option<RequestBody>) -> Result<Value> {
             let url = format!("{}/api/{}/{}", self.base_url, self.api_version, endpoint.trim_start_matches('/'));
-            let auth_header = self.generate_auth_header(method, &url);
-            let mut request = self.client.request(
-                method.parse().context("Invalid HTTP method")?,
-                &url,
-            );
-            request = request
-                .header(AUTHORIZATION, auth_header)
-                .header(CONTENT_TYPE, "application/json");
-
-            if let Some(body) = body {
-                request = request.json(&body);
-            }
+            self.send(method, &url, body)
+        }
 
-            let response = request.send().context("Failed to send request")?;
+        /// Builds the final `?op=...` URL for a MAAS operation call and performs it.
+        ///
+        /// The OAuth signature is computed over this final URL, `op` included, as
+        /// MAAS expects.
+        fn request_with_op(&self, method: &str, endpoint: &str, op: &str, body: RequestBody) -> Result<Value> {
+            let url = build_op_url(&self.base_url, &self.api_version, endpoint, op);
+            self.send(method, &url, Some(body))
+        }
 
-            let status = response.status();
-            if !status.is_success() {
-                let text = response.text().unwrap_or_default();
-                return Err(anyhow::anyhow!("MAAS Error {}: {}", status, text));
-            }
+        fn send(&self, method: &str, url: &str, body: Option<RequestBody>) -> Result<Value> {
+            let mut attempt: u32 = 0;
 
-            let json: Value = response.json().context("Failed to parse JSON response")?;
+            loop {
+                // regenerate on every attempt: the nonce/timestamp must be fresh
+                let auth_header = self.generate_auth_header(method, url);
+                let mut request = self.client.request(
+                    method.parse().context("Invalid HTTP method")?,
+                    url,
+                );
+                request = request.header(AUTHORIZATION, auth_header);
 
-            Ok(json)
+                request = match &body {
+                    Some(RequestBody::Json(value)) => request.header(CONTENT_TYPE, "application/json").json(value),
+                    Some(RequestBody::Form(pairs)) => request.form(pairs),
+                    None => request.header(CONTENT_TYPE, "application/json"),
+                };
+
+                match request.send() {
+                    Ok(response) => {
+                        let status = response.status();
+
+                        if status.is_success() {
+                            let json: Value = response.json().context("Failed to parse JSON response")?;
+                            return Ok(json);
+                        }
+
+                        if is_retryable_status(status) {
+                            if attempt >= self.max_retries {
+                                return Err(MaasError::RetriesExhausted { attempts: attempt + 1, last_status: Some(status) }.into());
+                            }
+
+                            let retry_after = response
+                                .headers()
+                                .get(reqwest::header::RETRY_AFTER)
+                                .and_then(|v| v.to_str().ok())
+                                .and_then(parse_retry_after);
+                            std::thread::sleep(backoff_delay(attempt, retry_after));
+                            attempt += 1;
+                            continue;
+                        }
+
+                        let text = response.text().unwrap_or_default();
+                        return Err(anyhow::anyhow!("MAAS Error {}: {}", status, text));
+                    }
+                    Err(err) if err.is_timeout() || err.is_connect() => {
+                        if attempt >= self.max_retries {
+                            return Err(MaasError::RetriesExhausted { attempts: attempt + 1, last_status: None }.into());
+                        }
+
+                        std::thread::sleep(backoff_delay(attempt, None));
+                        attempt += 1;
+                    }
+                    Err(err) => return Err(err).context("Failed to send request"),
+                }
+            }
         }
     }
 }
\ No newline at end of file
@@ -28,4 +28,15 @@ pub enum MaasError {
     // URL parsing has failed
     #[error("Invalid URL: {0}")]
     UrlParseError(#[from] url::ParseError),
+
+    // All retry attempts were used up without a successful response
+    #[error("Gave up after {attempts} attempt(s), last status: {last_status:?}")]
+    RetriesExhausted {
+        attempts: u32,
+        last_status: Option<reqwest::StatusCode>,
+    },
+
+    // The TLS configuration (root certificate, rustls setup) couldn't be built
+    #[error("TLS configuration error: {0}")]
+    TlsConfig(String),
 }
\ No newline at end of file
@@ -3,7 +3,8 @@ mod error;
 mod client;
 
 // Re-export what the user needs
-// pub use models::Machine;
+pub use models::{Machine, PowerState, Subnet, Tag};
 pub use error::MaasError;
+pub use client::MaasClientBuilder;
 #[cfg(feature = "async")]
 pub use client::client::MaasClient;
\ No newline at end of file
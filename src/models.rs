@@ -54,4 +54,51 @@ impl Machine {
             self.system_id, self.hostname, self.cpu_count, self.memory, self.status
         )
     }
+}
+
+/// Represents a MAAS tag, used to group and categorize machines.
+///
+/// This matches the JSON output from `GET /api/2.0/tags/`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Tag {
+    /// The tag's unique name (e.g. "gpu-node").
+    pub name: String,
+
+    /// Optional free-form description of what the tag means.
+    #[serde(default)]
+    pub comment: String,
+
+    /// Optional XPath definition used for automatic tagging.
+    #[serde(default)]
+    pub definition: String,
+
+    /// Kernel options applied to machines with this tag.
+    #[serde(rename = "kernel_opts", default)]
+    pub kernel_opts: Option<String>,
+}
+
+/// Represents a MAAS subnet.
+///
+/// This matches the JSON output from `GET /api/2.0/subnets/`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Subnet {
+    /// The subnet's numeric ID.
+    pub id: u64,
+
+    /// The user-defined name (e.g. "subnet-1").
+    pub name: String,
+
+    /// The CIDR notation of the subnet (e.g. "10.0.0.0/24").
+    pub cidr: String,
+
+    /// DNS servers handed out to machines on this subnet.
+    #[serde(rename = "dns_servers", default)]
+    pub dns_servers: Vec<String>,
+}
+
+/// The power state reported by `op=query_power_state`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PowerState {
+    /// The current power state (e.g. "on", "off", "error").
+    pub status: String,
 }
\ No newline at end of file